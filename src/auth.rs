@@ -3,6 +3,12 @@
 //! This module implements JWT-based authentication utilities.
 //! It defines the structure of our token claims and provides functions
 //! to create and validate JSON Web Tokens using the HS256 algorithm.
+//!
+//! Two kinds of token are issued: a short-lived *access* token that
+//! authorizes API calls, and a longer-lived *refresh* token that can be
+//! exchanged for a new access token via `POST /refresh` without the user
+//! re-entering credentials. The two are distinguished by the `typ` claim
+//! so one can't be used in place of the other.
 
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use serde::{Serialize, Deserialize};
@@ -10,33 +16,57 @@ use std::env;
 use uuid::Uuid;
 use chrono::{Utc, Duration};
 
+/// Default access token lifetime (1 hour), used when `JWT_ACCESS_TTL_SECS` is unset.
+const DEFAULT_ACCESS_TTL_SECS: i64 = 60 * 60;
+/// Default refresh token lifetime (30 days), used when `JWT_REFRESH_TTL_SECS` is unset.
+const DEFAULT_REFRESH_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Which kind of token a `Claims` value represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    Access,
+    Refresh,
+}
+
+impl TokenType {
+    fn as_str(self) -> &'static str {
+        match self {
+            TokenType::Access => "access",
+            TokenType::Refresh => "refresh",
+        }
+    }
+}
+
 /// The set of data we encode into each JWT.
 ///
-/// - `sub`: Subject, used here to store the user’s UUID as a string.
+/// - `sub`: Subject, used here to store the user's UUID as a string.
 /// - `exp`: Expiration timestamp, expressed as seconds since the epoch.
+/// - `typ`: Either `"access"` or `"refresh"`, so a refresh token can't be
+///   replayed as an access token or vice versa.
 #[derive(Serialize, Deserialize)]
 struct Claims {
     sub: String,
     exp: usize,
+    typ: String,
 }
 
-/// Generate a signed JWT for the specified `user_id`.
-///
-/// Workflow:
-/// 1. Load the secret key from `JWT_SECRET` env var (default: "secretkey").
-/// 2. Compute token expiration 1 hour from current UTC time.
-/// 3. Construct `Claims` struct and encode with HS256.
-/// 4. Panic only if encoding unexpectedly fails.
+fn ttl_secs(env_var: &str, default: i64) -> i64 {
+    env::var(env_var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
 
-pub fn create_token(user_id: Uuid) -> String {
+fn create_token(user_id: Uuid, token_type: TokenType, ttl: i64) -> String {
     let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secretkey".to_string());
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(1))
+        .checked_add_signed(Duration::seconds(ttl))
         .expect("Failed to compute expiration timestamp")
         .timestamp() as usize;
     let claims = Claims {
         sub: user_id.to_string(),
         exp: expiration,
+        typ: token_type.as_str().to_string(),
     };
 
     encode(
@@ -47,16 +77,32 @@ pub fn create_token(user_id: Uuid) -> String {
     .expect("Token creation failed")
 }
 
-/// Validate the JWT and return the user ID (`sub` claim) on success.
-/// Returns `None` if token is invalid or expired.
+/// Generate a signed access token for `user_id`, valid for
+/// `JWT_ACCESS_TTL_SECS` seconds (default: 1 hour).
+pub fn create_access_token(user_id: Uuid) -> String {
+    create_token(user_id, TokenType::Access, ttl_secs("JWT_ACCESS_TTL_SECS", DEFAULT_ACCESS_TTL_SECS))
+}
 
-pub fn validate_token(token: &str) -> Option<String> {
+/// Generate a signed refresh token for `user_id`, valid for
+/// `JWT_REFRESH_TTL_SECS` seconds (default: 30 days).
+pub fn create_refresh_token(user_id: Uuid) -> String {
+    create_token(user_id, TokenType::Refresh, ttl_secs("JWT_REFRESH_TTL_SECS", DEFAULT_REFRESH_TTL_SECS))
+}
+
+/// Validate the JWT and return the user ID (`sub` claim) on success,
+/// provided its `typ` claim matches `expected`. Returns `None` if the
+/// token is invalid, expired, or of the wrong kind (e.g. a refresh token
+/// presented where an access token is required).
+pub fn validate_token(token: &str, expected: TokenType) -> Option<String> {
     let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secretkey".to_string());
-    decode::<Claims>(
+    let data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret.as_ref()),
         &Validation::default(),
     )
-    .ok()
-    .map(|data| data.claims.sub)
-}
\ No newline at end of file
+    .ok()?;
+    if data.claims.typ != expected.as_str() {
+        return None;
+    }
+    Some(data.claims.sub)
+}
@@ -0,0 +1,39 @@
+//! Extracts and validates the authenticated user from a request's JWT so
+//! handlers no longer need to re-parse the `Authorization` header themselves.
+
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use uuid::Uuid;
+
+use crate::auth::{self, TokenType};
+use crate::error::ApiError;
+
+/// The authenticated user's id, extracted from a validated `Bearer` JWT.
+///
+/// Using this as a handler parameter yields an automatic `401` when the
+/// header is missing, malformed, or the token fails validation.
+pub struct AuthUser(pub Uuid);
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(extract(req).map(AuthUser))
+    }
+}
+
+fn extract(req: &HttpRequest) -> Result<Uuid, ApiError> {
+    let header = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+    let token = header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| ApiError::Unauthorized("Missing or malformed Authorization header".into()))?;
+    let sub = auth::validate_token(token, TokenType::Access)
+        .ok_or_else(|| ApiError::Unauthorized("Invalid token".into()))?;
+    Uuid::parse_str(&sub).map_err(|_| ApiError::Unauthorized("Invalid user ID in token".into()))
+}
@@ -0,0 +1,5 @@
+//! Actix-Web extractors shared across handlers.
+
+pub mod auth;
+
+pub use auth::AuthUser;
@@ -1,73 +1,126 @@
 //! HTTP route handlers for our REST API.
-//! Each handler enforces JWT auth (except `/login`), executes queries, and returns JSON or HTTP errors.
+//! Each handler enforces JWT auth (except `/login`, `/register`, `/refresh`
+//! and `/stocks/{symbol}`),
+//! executes queries, and returns JSON or a structured [`ApiError`].
 
-use actix_web::{web, HttpResponse, Responder, HttpRequest, Error};
-use actix_web::error::{ErrorUnauthorized, ErrorInternalServerError};
+use actix_web::{web, HttpResponse, Responder};
 use sqlx::{SqlitePool, Row};
 use uuid::Uuid;
-use crate::{auth, models::{Stock, Transaction}};
+use crate::{
+    auth::{self, TokenType},
+    error::ApiError,
+    extractors::AuthUser,
+    models::{Stock, Transaction},
+    password,
+    repository::portfolio,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use bcrypt;
 
 /// Payload for `/login`.
 #[derive(Deserialize)]
-pub struct LoginRequest { username: String, hashed_password: String }
+pub struct LoginRequest { username: String, password: String }
 
-/// Success response for `/login`.
+/// Payload for `/register`.
+#[derive(Deserialize)]
+pub struct RegisterRequest { username: String, password: String }
+
+/// Success response for `/login` and `/register`.
 #[derive(Serialize)]
-pub struct LoginResponse { token: String }
+pub struct LoginResponse { access_token: String, refresh_token: String }
+
+/// Payload for `/refresh`.
+#[derive(Deserialize)]
+pub struct RefreshRequest { refresh_token: String }
+
+/// Success response for `/refresh`.
+#[derive(Serialize)]
+pub struct RefreshResponse { access_token: String }
 
 /// Payload for buy/sell transactions.
 #[derive(Deserialize)]
 pub struct TransactionRequest { stock_id: Uuid, quantity: i32 }
 
-/// Extract and validate JWT from `Authorization` header.
-fn authorize(req: &HttpRequest) -> Result<Uuid, Error> {
-    let header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("");
-    let token = header
-        .strip_prefix("Bearer ")
-        .ok_or_else(|| ErrorUnauthorized("Missing or malformed Authorization header"))?;
-    let sub = auth::validate_token(token)
-        .ok_or_else(|| ErrorUnauthorized("Invalid token"))?;
-    Uuid::parse_str(&sub)
-        .map_err(|_| ErrorUnauthorized("Invalid user ID in token"))
-}
-
-/// POST /login: authenticate user and return JWT.
+/// POST /login: authenticate user and return an access/refresh token pair.
 pub async fn login(
     pool: web::Data<SqlitePool>,
     body: web::Json<LoginRequest>
-) -> impl Responder {
-    let row = match sqlx::query("SELECT id, hashed_password FROM users WHERE username = ?")
+) -> Result<impl Responder, ApiError> {
+    let row = sqlx::query("SELECT id, hashed_password FROM users WHERE username = ?")
+        .bind(&body.username)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid credentials".into()))?;
+
+    let user_id_str: String = row.try_get("id")
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let stored_hash: String = row.try_get("hashed_password")
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    if !password::verify(&body.password, &stored_hash) {
+        return Err(ApiError::Unauthorized("Invalid credentials".into()));
+    }
+    let user_id = Uuid::parse_str(&user_id_str)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    if password::needs_rehash(&stored_hash) {
+        let upgraded = password::hash(&body.password);
+        let _ = sqlx::query("UPDATE users SET hashed_password = ? WHERE id = ?")
+            .bind(&upgraded)
+            .bind(user_id.to_string())
+            .execute(pool.get_ref())
+            .await;
+    }
+
+    let access_token = auth::create_access_token(user_id);
+    let refresh_token = auth::create_refresh_token(user_id);
+    Ok(HttpResponse::Ok().json(LoginResponse { access_token, refresh_token }))
+}
+
+/// POST /register: create a new user and return a token pair so the client
+/// is immediately logged in.
+pub async fn register(
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RegisterRequest>
+) -> Result<impl Responder, ApiError> {
+    let existing = sqlx::query("SELECT id FROM users WHERE username = ?")
         .bind(&body.username)
-        .fetch_one(pool.get_ref())
-        .await
-    {
-        Ok(r) => r,
-        Err(_) => return HttpResponse::Unauthorized().body("Invalid credentials"),
-    };
-    let user_id_str: String = row.try_get("id").unwrap_or_default();
-    let stored_hash: String = row.try_get("hashed_password").unwrap_or_default();
-    if !bcrypt::verify(&body.hashed_password, &stored_hash).unwrap_or(false) {
-        return HttpResponse::Unauthorized().body("Invalid credentials");
+        .fetch_optional(pool.get_ref())
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::Conflict("Username already taken".into()));
     }
-    let user_id = Uuid::parse_str(&user_id_str).unwrap();
-    let token = auth::create_token(user_id);
-    HttpResponse::Ok().json(LoginResponse { token })
+
+    let hashed_password = password::hash(&body.password);
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, ?, ?)")
+        .bind(user_id.to_string())
+        .bind(&body.username)
+        .bind(&hashed_password)
+        .execute(pool.get_ref())
+        .await?;
+
+    let access_token = auth::create_access_token(user_id);
+    let refresh_token = auth::create_refresh_token(user_id);
+    Ok(HttpResponse::Ok().json(LoginResponse { access_token, refresh_token }))
+}
+
+/// POST /refresh: exchange a valid refresh token for a new access token.
+pub async fn refresh(body: web::Json<RefreshRequest>) -> Result<impl Responder, ApiError> {
+    let sub = auth::validate_token(&body.refresh_token, TokenType::Refresh)
+        .ok_or_else(|| ApiError::Unauthorized("Invalid or expired refresh token".into()))?;
+    let user_id = Uuid::parse_str(&sub)
+        .map_err(|_| ApiError::Unauthorized("Invalid user ID in token".into()))?;
+    let access_token = auth::create_access_token(user_id);
+    Ok(HttpResponse::Ok().json(RefreshResponse { access_token }))
 }
 
 /// POST /buy: record a buy transaction directly.
 pub async fn buy_stock(
     pool: web::Data<SqlitePool>,
-    req: HttpRequest,
+    user: AuthUser,
     body: web::Json<TransactionRequest>
-) -> Result<impl Responder, Error> {
-    let user_id = authorize(&req)?;
+) -> Result<impl Responder, ApiError> {
+    let user_id = user.0;
     let id = Uuid::new_v4();
     sqlx::query(
         "INSERT INTO transactions (id, user_id, stock_id, quantity, transaction_type) VALUES (?, ?, ?, ?, ?)"
@@ -78,8 +131,7 @@ pub async fn buy_stock(
     .bind(body.quantity)
     .bind("buy")
     .execute(pool.get_ref())
-    .await
-    .map_err(|_| ErrorInternalServerError("Failed to record buy transaction"))?;
+    .await?;
     Ok(HttpResponse::Ok().json(json!({
         "id": id,
         "user_id": user_id,
@@ -89,13 +141,21 @@ pub async fn buy_stock(
     })))
 }
 
-/// POST /sell: record a sell transaction directly.
+/// POST /sell: record a sell transaction, rejecting it if it would leave
+/// the user's held quantity negative.
 pub async fn sell_stock(
     pool: web::Data<SqlitePool>,
-    req: HttpRequest,
+    user: AuthUser,
     body: web::Json<TransactionRequest>
-) -> Result<impl Responder, Error> {
-    let user_id = authorize(&req)?;
+) -> Result<impl Responder, ApiError> {
+    let user_id = user.0;
+    let mut tx = pool.begin().await?;
+
+    let held = portfolio::net_quantity(&mut *tx, user_id, body.stock_id).await?;
+    if i64::from(body.quantity) > held {
+        return Err(ApiError::BadRequest("Cannot sell more shares than you hold".into()));
+    }
+
     let id = Uuid::new_v4();
     sqlx::query(
         "INSERT INTO transactions (id, user_id, stock_id, quantity, transaction_type) VALUES (?, ?, ?, ?, ?)"
@@ -105,9 +165,11 @@ pub async fn sell_stock(
     .bind(body.stock_id.to_string())
     .bind(body.quantity)
     .bind("sell")
-    .execute(pool.get_ref())
-    .await
-    .map_err(|_| ErrorInternalServerError("Failed to record sell transaction"))?;
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
     Ok(HttpResponse::Ok().json(json!({
         "id": id,
         "user_id": user_id,
@@ -117,69 +179,73 @@ pub async fn sell_stock(
     })))
 }
 
+/// GET /portfolio: list the authenticated user's current stock holdings.
+pub async fn get_portfolio(
+    pool: web::Data<SqlitePool>,
+    user: AuthUser
+) -> Result<impl Responder, ApiError> {
+    let holdings = portfolio::holdings(pool.get_ref(), user.0).await?;
+    Ok(HttpResponse::Ok().json(holdings))
+}
+
 /// GET /transactions: retrieve all transactions for the authenticated user.
 pub async fn get_transactions(
     pool: web::Data<SqlitePool>,
-    req: HttpRequest
-) -> Result<impl Responder, Error> {
-    let user_id = authorize(&req)?;
+    user: AuthUser
+) -> Result<impl Responder, ApiError> {
+    let user_id = user.0;
     let rows = sqlx::query(
         "SELECT id, user_id, stock_id, quantity, transaction_type FROM transactions WHERE user_id = ? ORDER BY created_at DESC"
     )
     .bind(user_id.to_string())
     .fetch_all(pool.get_ref())
-    .await
-    .map_err(|_| ErrorInternalServerError("Failed to query transactions"))?;
+    .await?;
     let mut results = Vec::with_capacity(rows.len());
     for row in rows {
-        let id_str: String = row.try_get("id").map_err(|_| ErrorInternalServerError("Invalid `id` field"))?;
-        let uid_str: String = row.try_get("user_id").map_err(|_| ErrorInternalServerError("Invalid `user_id` field"))?;
-        let sid_str: String = row.try_get("stock_id").map_err(|_| ErrorInternalServerError("Invalid `stock_id` field"))?;
-        let quantity: i32 = row.try_get("quantity").map_err(|_| ErrorInternalServerError("Invalid `quantity` field"))?;
-        let transaction_type: String = row.try_get("transaction_type").map_err(|_| ErrorInternalServerError("Invalid `transaction_type` field"))?;
-        let transaction = Transaction { id: Uuid::parse_str(&id_str).map_err(|_| ErrorInternalServerError("Invalid UUID in `id`"))?, user_id: Uuid::parse_str(&uid_str).map_err(|_| ErrorInternalServerError("Invalid UUID in `user_id`"))?, stock_id: Uuid::parse_str(&sid_str).map_err(|_| ErrorInternalServerError("Invalid UUID in `stock_id`"))?, quantity, transaction_type };
+        let id_str: String = row.try_get("id").map_err(|e| ApiError::Internal(e.to_string()))?;
+        let uid_str: String = row.try_get("user_id").map_err(|e| ApiError::Internal(e.to_string()))?;
+        let sid_str: String = row.try_get("stock_id").map_err(|e| ApiError::Internal(e.to_string()))?;
+        let quantity: i32 = row.try_get("quantity").map_err(|e| ApiError::Internal(e.to_string()))?;
+        let transaction_type: String = row.try_get("transaction_type").map_err(|e| ApiError::Internal(e.to_string()))?;
+        let transaction = Transaction {
+            id: Uuid::parse_str(&id_str).map_err(|e| ApiError::Internal(e.to_string()))?,
+            user_id: Uuid::parse_str(&uid_str).map_err(|e| ApiError::Internal(e.to_string()))?,
+            stock_id: Uuid::parse_str(&sid_str).map_err(|e| ApiError::Internal(e.to_string()))?,
+            quantity,
+            transaction_type,
+        };
         results.push(transaction);
     }
     Ok(HttpResponse::Ok().json(results))
 }
 
-/// GET /stocks/{symbol}: fetch a stock by its symbol.
+/// GET /stocks/{symbol}: fetch a stock by its symbol. Distinguishes a
+/// genuinely missing symbol (`404`) from an internal database failure (`500`).
 pub async fn get_stock(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>
-) -> impl Responder {
+) -> Result<impl Responder, ApiError> {
     let symbol = path.into_inner();
-    eprintln!("ðŸ” get_stock called with symbol = {:?}", symbol);
-    let row = match sqlx::query(
-        "SELECT id, symbol, price FROM stocks WHERE symbol = ?"
-    )
-    .bind(&symbol)
-    .fetch_one(pool.get_ref())
-    .await
-    {
-        Ok(r) => r,
-        Err(err) => {
-            eprintln!("âš ï¸  get_stock error: {:?}", err);
-            return HttpResponse::NotFound().body("Stock not found or DB error");
-        }
-    };
-    let id_str: String = match row.try_get("id") {
-        Ok(s) => s,
-        Err(_) => return HttpResponse::InternalServerError().body("Invalid `id` field"),
-    };
-    let price: f64 = match row.try_get("price") {
-        Ok(p) => p,
-        Err(_) => return HttpResponse::InternalServerError().body("Invalid `price` field"),
-    };
-    let stock = Stock { id: match Uuid::parse_str(&id_str) { Ok(u) => u, Err(_) => return HttpResponse::InternalServerError().body("Invalid UUID in `id`"), }, symbol, price };
-    HttpResponse::Ok().json(stock)
+    let row = sqlx::query("SELECT id, symbol, price FROM stocks WHERE symbol = ?")
+        .bind(&symbol)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No stock with symbol `{symbol}`")))?;
+
+    let id_str: String = row.try_get("id").map_err(|e| ApiError::Internal(e.to_string()))?;
+    let price: f64 = row.try_get("price").map_err(|e| ApiError::Internal(e.to_string()))?;
+    let id = Uuid::parse_str(&id_str).map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(Stock { id, symbol, price }))
 }
 
 /// Register all routes with Actix.
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/login").route(web::post().to(login)));
+    cfg.service(web::resource("/register").route(web::post().to(register)));
+    cfg.service(web::resource("/refresh").route(web::post().to(refresh)));
     cfg.service(web::resource("/buy").route(web::post().to(buy_stock)));
     cfg.service(web::resource("/sell").route(web::post().to(sell_stock)));
     cfg.service(web::resource("/transactions").route(web::get().to(get_transactions)));
+    cfg.service(web::resource("/portfolio").route(web::get().to(get_portfolio)));
     cfg.service(web::resource("/stocks/{symbol}").route(web::get().to(get_stock)));
-}
\ No newline at end of file
+}
@@ -10,15 +10,41 @@
 //! 3. Reads server bind address from env.
 //! 4. Configures routes and launches the HTTP server.
 
-use actix_web::{web, App, HttpServer};
+use actix_cors::Cors;
+use actix_web::{http, web, App, HttpServer};
 use dotenv::dotenv;
 use std::env;
 
 mod db;    // DB initialization and migrations
 mod auth;  // JWT auth helpers
 mod models;// Data models and FromRow derivations
+mod password; // Password hashing (Argon2id, with legacy bcrypt support)
+mod extractors; // Request extractors (e.g. AuthUser)
+mod repository; // Data-access code grouped by domain concept (e.g. portfolio)
+mod error; // Crate-wide ApiError and its ResponseError impl
 mod handlers;// HTTP route handlers
 
+/// Default allowed origins when `CORS_ALLOWED_ORIGINS` is unset, covering
+/// the usual local dev frontend ports.
+const DEFAULT_CORS_ORIGINS: &str = "http://127.0.0.1:3000,http://localhost:3000";
+
+/// Build the CORS layer from `CORS_ALLOWED_ORIGINS` (a comma-separated
+/// list of origins), so a browser-based frontend on another origin can
+/// call this API.
+fn cors_layer() -> Cors {
+    let origins = env::var("CORS_ALLOWED_ORIGINS")
+        .unwrap_or_else(|_| DEFAULT_CORS_ORIGINS.to_string());
+
+    let mut cors = Cors::default()
+        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+        .allowed_headers(vec![http::header::AUTHORIZATION, http::header::CONTENT_TYPE])
+        .max_age(3600);
+    for origin in origins.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        cors = cors.allowed_origin(origin);
+    }
+    cors
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load .env (DATABASE_URL, JWT_SECRET, SERVER_HOST, SERVER_PORT)
@@ -44,6 +70,7 @@ async fn main() -> std::io::Result<()> {
     // Start HTTP server with configured routes
     HttpServer::new(move || {
         App::new()
+            .wrap(cors_layer())
             .app_data(web::Data::new(db_pool.clone()))
             .configure(handlers::config)
     })
@@ -10,7 +10,9 @@ use sqlx::FromRow;
 /// Fields:
 /// - `id`: Primary key (UUID).
 /// - `username`: Unique login name.
-/// - `hashed_password`: Bcrypt hash of the user’s password.
+/// - `hashed_password`: Argon2id PHC hash of the user's password (or,
+///   for rows created before the Argon2 migration, a legacy bcrypt hash —
+///   see [`crate::password`]).
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -0,0 +1,46 @@
+//! Password hashing for user credentials.
+//!
+//! Argon2id (PHC string format) is the current algorithm. Existing rows may
+//! still hold a bcrypt hash (recognizable by its `$2` prefix); [`verify`]
+//! accepts either, and callers can use [`needs_rehash`] to detect a legacy
+//! hash and transparently upgrade it to Argon2id after a successful login.
+//! Keeping this behind one module means the algorithm can change again
+//! without touching the handlers that call it.
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// Hash `plaintext` with Argon2id and return the PHC string to store in
+/// `users.hashed_password`.
+pub fn hash(plaintext: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(plaintext.as_bytes(), &salt)
+        .expect("Argon2 hashing failed")
+        .to_string()
+}
+
+/// Verify `plaintext` against a stored hash, whether it's an Argon2id PHC
+/// string or a legacy bcrypt hash.
+pub fn verify(plaintext: &str, stored_hash: &str) -> bool {
+    if is_bcrypt(stored_hash) {
+        return bcrypt::verify(plaintext, stored_hash).unwrap_or(false);
+    }
+    match PasswordHash::new(stored_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(plaintext.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Whether `stored_hash` is a legacy bcrypt hash that should be upgraded to
+/// Argon2id the next time it's successfully verified.
+pub fn needs_rehash(stored_hash: &str) -> bool {
+    is_bcrypt(stored_hash)
+}
+
+fn is_bcrypt(stored_hash: &str) -> bool {
+    stored_hash.starts_with("$2")
+}
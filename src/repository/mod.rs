@@ -0,0 +1,4 @@
+//! Data-access code grouped by domain concept, separate from the HTTP
+//! handlers that call into it.
+
+pub mod portfolio;
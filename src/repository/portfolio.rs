@@ -0,0 +1,80 @@
+//! Computes a user's stock holdings from the `transactions` ledger.
+//!
+//! There is no separate "holdings" table: a user's position in a stock is
+//! always derived by summing signed transaction quantities (`buy` positive,
+//! `sell` negative). Functions here take a generic `sqlx::Executor` so they
+//! can run against either a pooled connection or an open transaction, which
+//! lets `sell_stock` check the held quantity and insert the sell atomically.
+
+use serde::Serialize;
+use sqlx::{Executor, Row, Sqlite};
+use uuid::Uuid;
+
+/// A stock position: how many shares the user holds and what they're worth
+/// at the stock's current price.
+#[derive(Debug, Serialize)]
+pub struct Holding {
+    pub stock_id: Uuid,
+    pub symbol: String,
+    pub quantity: i64,
+    pub price: f64,
+    pub market_value: f64,
+}
+
+/// Net shares of `stock_id` held by `user_id` (buys minus sells).
+pub async fn net_quantity<'e, E>(executor: E, user_id: Uuid, stock_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(
+        "SELECT COALESCE(SUM(CASE transaction_type \
+            WHEN 'buy' THEN quantity \
+            WHEN 'sell' THEN -quantity \
+            ELSE 0 END), 0) AS net \
+         FROM transactions WHERE user_id = ? AND stock_id = ?"
+    )
+    .bind(user_id.to_string())
+    .bind(stock_id.to_string())
+    .fetch_one(executor)
+    .await?;
+    row.try_get::<i64, _>("net")
+}
+
+/// All stocks `user_id` currently holds a positive position in, along with
+/// the current price and resulting market value.
+pub async fn holdings<'e, E>(executor: E, user_id: Uuid) -> Result<Vec<Holding>, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows = sqlx::query(
+        "SELECT s.id AS stock_id, s.symbol AS symbol, s.price AS price, \
+            COALESCE(SUM(CASE t.transaction_type \
+                WHEN 'buy' THEN t.quantity \
+                WHEN 'sell' THEN -t.quantity \
+                ELSE 0 END), 0) AS net_quantity \
+         FROM transactions t \
+         JOIN stocks s ON s.id = t.stock_id \
+         WHERE t.user_id = ? \
+         GROUP BY s.id \
+         HAVING net_quantity > 0"
+    )
+    .bind(user_id.to_string())
+    .fetch_all(executor)
+    .await?;
+
+    let mut holdings = Vec::with_capacity(rows.len());
+    for row in rows {
+        let stock_id_str: String = row.try_get("stock_id")?;
+        let symbol: String = row.try_get("symbol")?;
+        let price: f64 = row.try_get("price")?;
+        let quantity: i64 = row.try_get("net_quantity")?;
+        holdings.push(Holding {
+            stock_id: Uuid::parse_str(&stock_id_str).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            symbol,
+            quantity,
+            price,
+            market_value: price * quantity as f64,
+        });
+    }
+    Ok(holdings)
+}